@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{
   atomic::{AtomicBool, Ordering},
   Arc, Mutex,
@@ -35,12 +35,24 @@ struct NativeEvent {
 
 enum ControlMessage {
   PushFrame(Vec<i16>),
+  SetWhisperTarget(Option<WhisperTarget>),
+  SetMasterVolume(f32),
+  SetClientVolume(ClientId, f32),
   Disconnect {
     message: Option<String>,
     done: oneshot::Sender<Result<(), String>>,
   },
 }
 
+/// A directed-audio target set via `setWhisperTarget`: outgoing audio is
+/// sent as `AudioData::C2SWhisper` to only these clients/channels instead of
+/// broadcast to the whole channel.
+#[derive(Clone)]
+struct WhisperTarget {
+  clients: Vec<u16>,
+  channels: Vec<u64>,
+}
+
 #[napi(object)]
 pub struct ConnectOptions {
   pub address: String,
@@ -50,6 +62,124 @@ pub struct ConnectOptions {
   pub channel_password: Option<String>,
   pub identity: Option<String>,
   pub log_level: Option<String>,
+  /// Selects the Opus tuning for outgoing audio: "voip" (default) favors
+  /// speech, "music" favors wideband content such as relayed tracks.
+  pub audio_profile: Option<String>,
+  /// Target bitrate in bits/s for the Opus encoder; encoder default if unset.
+  pub bitrate: Option<i32>,
+  /// Enables variable bitrate encoding (the `audiopus` default is already
+  /// VBR-on, so this is mainly useful to explicitly force CBR with `false`).
+  pub vbr: Option<bool>,
+  /// Sample rate of buffers passed to `pushFrame`, if not already 48kHz
+  /// (e.g. 44100 from a file); 48kHz is assumed if unset.
+  pub input_sample_rate: Option<u32>,
+  /// Channel count of buffers passed to `pushFrame`, if not already mono
+  /// (e.g. stereo from a Discord/WebRTC source); downmixed by averaging.
+  pub input_channels: Option<u8>,
+  /// Opt-in policy for rebuilding the connection after a non-graceful stream
+  /// error instead of tearing the worker down. Absent disables reconnecting,
+  /// matching the previous terminate-on-error behavior.
+  pub auto_reconnect: Option<AutoReconnectOptions>,
+}
+
+#[napi(object)]
+#[derive(Clone)]
+pub struct AutoReconnectOptions {
+  /// Maximum number of reconnect attempts before giving up and emitting a
+  /// final `disconnected` event.
+  pub max_attempts: u32,
+  /// Delay before the first reconnect attempt, in milliseconds.
+  pub base_delay_ms: u32,
+  /// Factor the delay is multiplied by after each failed attempt.
+  pub multiplier: f64,
+  /// Upper bound on the backoff delay, in milliseconds.
+  pub max_delay_ms: u32,
+}
+
+/// The two outgoing-audio tunings exposed via `ConnectOptions::audio_profile`.
+#[derive(Clone, Copy)]
+enum AudioProfile {
+  Voip,
+  Music,
+}
+
+impl AudioProfile {
+  fn parse(raw: Option<&str>) -> Self {
+    match raw {
+      Some("music") => AudioProfile::Music,
+      _ => AudioProfile::Voip,
+    }
+  }
+
+  fn codec(self) -> CodecType {
+    match self {
+      AudioProfile::Voip => CodecType::OpusVoice,
+      AudioProfile::Music => CodecType::OpusMusic,
+    }
+  }
+
+  fn application(self) -> audiopus::Application {
+    match self {
+      AudioProfile::Voip => audiopus::Application::Voip,
+      AudioProfile::Music => audiopus::Application::Audio,
+    }
+  }
+}
+
+/// Computes backoff delays for `ConnectOptions::auto_reconnect`; `None`
+/// disables reconnecting entirely, matching the prior terminate-on-error
+/// behavior.
+struct ReconnectPolicy {
+  max_attempts: u32,
+  base_delay_ms: u32,
+  multiplier: f64,
+  max_delay_ms: u32,
+}
+
+impl ReconnectPolicy {
+  fn from_opts(opts: Option<&AutoReconnectOptions>) -> Option<Self> {
+    opts.map(|o| Self {
+      max_attempts: o.max_attempts,
+      base_delay_ms: o.base_delay_ms,
+      multiplier: o.multiplier,
+      max_delay_ms: o.max_delay_ms,
+    })
+  }
+
+  /// Whether another attempt is allowed after `attempts_made` failed tries.
+  fn allows(&self, attempts_made: u32) -> bool { attempts_made < self.max_attempts }
+
+  /// The backoff delay before the attempt numbered `attempt_number` (1-based).
+  fn delay_for(&self, attempt_number: u32) -> Duration {
+    let factor = self.multiplier.powi(attempt_number.saturating_sub(1) as i32);
+    let delay_ms = (self.base_delay_ms as f64 * factor) as u64;
+    Duration::from_millis(delay_ms.min(self.max_delay_ms as u64))
+  }
+}
+
+/// The subset of `ConnectOptions` needed to (re)build a `Connection`, cloned
+/// once up front so `build_connection` and `try_reconnect` can be handed a
+/// single `&ConnectionParams` instead of each field individually.
+struct ConnectionParams {
+  address: String,
+  password: Option<String>,
+  nickname: Option<String>,
+  channel: Option<String>,
+  channel_password: Option<String>,
+  log_level: Option<String>,
+}
+
+impl ConnectionParams {
+  fn from_opts(opts: &ConnectOptions) -> Self {
+    Self {
+      address: opts.address.clone(),
+      password: opts.password.clone(),
+      nickname: opts.nickname.clone(),
+      channel: opts.channel.clone(),
+      channel_password: opts.channel_password.clone(),
+      log_level: opts.log_level.clone(),
+    }
+  }
 }
 
 #[napi(object)]
@@ -58,6 +188,41 @@ pub struct DisconnectParams {
   pub reason_code: Option<u32>,
 }
 
+/// Running connection-health counters, updated from the worker task and
+/// pulled by `getStats()`; also emitted periodically as a `stats` event.
+#[derive(Default, Clone)]
+struct StatsCounters {
+  frames_sent: u64,
+  bytes_sent: u64,
+  decode_drops_late: u64,
+  decode_drops_full_queue: u64,
+  active_speakers: u32,
+  jitter_buffer_depth: u32,
+}
+
+#[napi(object)]
+pub struct StatsSnapshot {
+  pub frames_sent: i64,
+  pub bytes_sent: i64,
+  pub decode_drops_late: i64,
+  pub decode_drops_full_queue: i64,
+  pub active_speakers: i64,
+  pub jitter_buffer_depth: i64,
+}
+
+impl From<StatsCounters> for StatsSnapshot {
+  fn from(s: StatsCounters) -> Self {
+    Self {
+      frames_sent: s.frames_sent as i64,
+      bytes_sent: s.bytes_sent as i64,
+      decode_drops_late: s.decode_drops_late as i64,
+      decode_drops_full_queue: s.decode_drops_full_queue as i64,
+      active_speakers: s.active_speakers as i64,
+      jitter_buffer_depth: s.jitter_buffer_depth as i64,
+    }
+  }
+}
+
 #[napi]
 pub struct TeamSpeakClient {
   event_tsfn: Arc<Mutex<Option<EventTsfn>>>,
@@ -65,6 +230,7 @@ pub struct TeamSpeakClient {
   join: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
   connected: Arc<AtomicBool>,
   identity: Arc<Mutex<Option<String>>>,
+  stats: Arc<Mutex<StatsCounters>>,
 }
 
 #[napi]
@@ -77,6 +243,7 @@ impl TeamSpeakClient {
       join: Arc::new(Mutex::new(None)),
       connected: Arc::new(AtomicBool::new(false)),
       identity: Arc::new(Mutex::new(None)),
+      stats: Arc::new(Mutex::new(StatsCounters::default())),
     }
   }
 
@@ -113,12 +280,14 @@ impl TeamSpeakClient {
     let (ready_tx, ready_rx) = oneshot::channel();
 
     self.connected.store(false, Ordering::SeqCst);
+    *self.stats.lock().expect("stats mutex poisoned") = StatsCounters::default();
 
     let event_tsfn = self.event_tsfn.clone();
     let connected = self.connected.clone();
+    let stats = self.stats.clone();
 
     let join = tokio::spawn(async move {
-      run_client(opts, identity, control_rx, event_tsfn, connected, ready_tx).await;
+      run_client(opts, identity, control_rx, event_tsfn, connected, stats, ready_tx).await;
     });
 
     *self.control_tx.lock().expect("control mutex poisoned") = Some(control_tx);
@@ -220,6 +389,29 @@ impl TeamSpeakClient {
     })
   }
 
+  #[napi(js_name = "setWhisperTarget")]
+  pub fn set_whisper_target(&self, client_ids: Option<Vec<u32>>, channel_ids: Option<Vec<u32>>) -> napi::Result<()> {
+    self.send_control(ControlMessage::SetWhisperTarget(Some(WhisperTarget {
+      clients: client_ids.unwrap_or_default().into_iter().map(|id| id as u16).collect(),
+      channels: channel_ids.unwrap_or_default().into_iter().map(|id| id as u64).collect(),
+    })))
+  }
+
+  #[napi(js_name = "clearWhisperTarget")]
+  pub fn clear_whisper_target(&self) -> napi::Result<()> {
+    self.send_control(ControlMessage::SetWhisperTarget(None))
+  }
+
+  #[napi(js_name = "setMasterVolume")]
+  pub fn set_master_volume(&self, volume: f64) -> napi::Result<()> {
+    self.send_control(ControlMessage::SetMasterVolume(volume as f32))
+  }
+
+  #[napi(js_name = "setClientVolume")]
+  pub fn set_client_volume(&self, client_id: u32, volume: f64) -> napi::Result<()> {
+    self.send_control(ControlMessage::SetClientVolume(ClientId(client_id as u16), volume as f32))
+  }
+
   #[napi(js_name = "isConnected")]
   pub fn is_connected(&self) -> bool {
     self.connected.load(Ordering::SeqCst)
@@ -234,9 +426,29 @@ impl TeamSpeakClient {
   pub fn get_identity(&self) -> Option<String> {
     self.identity.lock().expect("identity mutex poisoned").clone()
   }
+
+  #[napi(js_name = "getStats")]
+  pub fn get_stats(&self) -> StatsSnapshot {
+    self.stats.lock().expect("stats mutex poisoned").clone().into()
+  }
 }
 
 impl TeamSpeakClient {
+  fn send_control(&self, message: ControlMessage) -> napi::Result<()> {
+    self.refresh_worker_state();
+
+    let tx = {
+      let guard = self.control_tx.lock().expect("control mutex poisoned");
+      guard.clone()
+    };
+    let Some(tx) = tx else {
+      return Err(Error::new(Status::InvalidArg, "Not connected".to_string()));
+    };
+
+    tx.try_send(message)
+      .map_err(|_| Error::new(Status::GenericFailure, "Control queue is full or closed".to_string()))
+  }
+
   fn refresh_worker_state(&self) {
     let finished = self
       .join
@@ -260,40 +472,17 @@ async fn run_client(
   mut control_rx: mpsc::Receiver<ControlMessage>,
   event_tsfn: Arc<Mutex<Option<EventTsfn>>>,
   connected: Arc<AtomicBool>,
+  stats: Arc<Mutex<StatsCounters>>,
   ready_tx: oneshot::Sender<Result<(), String>>,
 ) {
   let mut ready_tx = Some(ready_tx);
 
-  let mut builder = Connection::build(opts.address.clone()).identity(identity);
-  if let Some(password) = opts.password {
-    builder = builder.password(password);
-  }
-  if let Some(nick) = opts.nickname {
-    builder = builder.name(nick);
-  }
-  if let Some(channel) = opts.channel {
-    builder = builder.channel(channel);
-  }
-  if let Some(channel_password) = opts.channel_password {
-    builder = builder.channel_password(channel_password);
-  }
-  match opts.log_level.as_deref() {
-    Some("commands") => {
-      builder = builder.log_commands(true);
-    }
-    Some("packets") => {
-      builder = builder.log_commands(true).log_packets(true);
-    }
-    Some("udp") => {
-      builder = builder.log_commands(true).log_packets(true).log_udp_packets(true);
-    }
-    _ => {}
-  }
+  let conn_params = ConnectionParams::from_opts(&opts);
+  let reconnect_policy = ReconnectPolicy::from_opts(opts.auto_reconnect.as_ref());
 
-  let con = match builder.connect() {
+  let con = match build_connection(&conn_params, identity.clone()) {
     Ok(c) => c,
-    Err(e) => {
-      let msg = format!("Failed to connect: {e}");
+    Err(msg) => {
       if let Some(tx) = ready_tx.take() {
         let _ = tx.send(Err(msg.clone()));
       }
@@ -303,11 +492,8 @@ async fn run_client(
   };
   let mut sync_con: SyncConnection = con.into();
 
-  let encoder = match Encoder::new(
-    audiopus::SampleRate::Hz48000,
-    audiopus::Channels::Mono,
-    audiopus::Application::Voip,
-  ) {
+  let audio_profile = AudioProfile::parse(opts.audio_profile.as_deref());
+  let encoder = match build_encoder(audio_profile, opts.bitrate, opts.vbr) {
     Ok(e) => e,
     Err(e) => {
       let msg = format!("Failed to create Opus encoder: {e}");
@@ -320,37 +506,76 @@ async fn run_client(
   };
   let mut opus_out = [0u8; MAX_OPUS_FRAME_SIZE];
 
+  let mut resampler =
+    Resampler::new(opts.input_sample_rate.unwrap_or(SAMPLE_RATE as u32) as usize, opts.input_channels.unwrap_or(1) as usize);
+  let mut frame_buffer: VecDeque<f32> = VecDeque::new();
+  let mut whisper_target: Option<WhisperTarget> = None;
+
   let mut speaker_handlers: HashMap<ClientId, AudioHandler<ClientId>> = HashMap::new();
+  let mut master_volume: f32 = 1.0;
+  let mut client_volumes: HashMap<ClientId, f32> = HashMap::new();
+  let mut limiter = SoftLimiter::new();
 
   let mut ticker = time::interval(Duration::from_millis(20));
   ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
-
-  loop {
+  // Speaker count and jitter-buffer depth are sampled, and stats emitted, only
+  // once a second (50 ticks at 20ms each) so listeners get a steady heartbeat
+  // instead of a 50Hz stream.
+  const STATS_EMIT_TICKS: u32 = 50;
+  let mut stats_tick: u32 = 0;
+  let mut reconnect_attempt: u32 = 0;
+
+  'connection: loop {
     tokio::select! {
       Some(ctrl) = control_rx.recv() => {
         match ctrl {
           ControlMessage::PushFrame(samples) => {
-            for frame in to_frames(&samples) {
+            frame_buffer.extend(resampler.process(&samples));
+
+            while frame_buffer.len() >= FRAME_SAMPLES {
               let mut input = [0.0f32; FRAME_SAMPLES];
-              for (dst, src) in input.iter_mut().zip(frame.iter()) {
-                *dst = *src as f32 / i16::MAX as f32;
+              for slot in input.iter_mut() {
+                *slot = frame_buffer.pop_front().expect("checked length above");
               }
 
               match encoder.encode_float(&input, &mut opus_out) {
                 Ok(len) => {
-                  let packet = OutAudio::new(&AudioData::C2S {
-                    id: 0,
-                    codec: CodecType::OpusVoice,
-                    data: &opus_out[..len],
-                  });
-                  if let Err(e) = sync_con.send_audio(packet) {
-                    emit_error(&event_tsfn, "E_SEND_AUDIO", &format!("{e}"));
+                  let packet = match &whisper_target {
+                    Some(target) => OutAudio::new(&AudioData::C2SWhisper {
+                      id: 0,
+                      codec: audio_profile.codec(),
+                      channels: &target.channels,
+                      clients: &target.clients,
+                      data: &opus_out[..len],
+                    }),
+                    None => OutAudio::new(&AudioData::C2S {
+                      id: 0,
+                      codec: audio_profile.codec(),
+                      data: &opus_out[..len],
+                    }),
+                  };
+                  match sync_con.send_audio(packet) {
+                    Ok(()) => {
+                      let mut stats = stats.lock().expect("stats mutex poisoned");
+                      stats.frames_sent += 1;
+                      stats.bytes_sent += len as u64;
+                    }
+                    Err(e) => emit_error(&event_tsfn, "E_SEND_AUDIO", &format!("{e}")),
                   }
                 }
                 Err(e) => emit_error(&event_tsfn, "E_AUDIO_ENCODE", &format!("{e}")),
               }
             }
           }
+          ControlMessage::SetWhisperTarget(target) => {
+            whisper_target = target;
+          }
+          ControlMessage::SetMasterVolume(volume) => {
+            master_volume = volume;
+          }
+          ControlMessage::SetClientVolume(client_id, volume) => {
+            client_volumes.insert(client_id, volume);
+          }
           ControlMessage::Disconnect { message, done } => {
             let mut options = DisconnectOptions::new();
             if let Some(message) = message {
@@ -414,7 +639,27 @@ async fn run_client(
         }
       }
       _ = ticker.tick() => {
-        emit_audio_frames(&event_tsfn, &mut speaker_handlers);
+        emit_audio_frames(&event_tsfn, &mut speaker_handlers, master_volume, &client_volumes, &mut limiter);
+
+        stats_tick += 1;
+        if stats_tick >= STATS_EMIT_TICKS {
+          stats_tick = 0;
+          let snapshot = {
+            let mut stats = stats.lock().expect("stats mutex poisoned");
+            stats.active_speakers = speaker_handlers.len() as u32;
+            stats.jitter_buffer_depth =
+              speaker_handlers.values_mut().map(|h| h.get_queues().len() as u32).sum();
+            stats.clone()
+          };
+          emit(&event_tsfn, "stats", serde_json::json!({
+            "framesSent": snapshot.frames_sent,
+            "bytesSent": snapshot.bytes_sent,
+            "decodeDropsLate": snapshot.decode_drops_late,
+            "decodeDropsFullQueue": snapshot.decode_drops_full_queue,
+            "activeSpeakers": snapshot.active_speakers,
+            "jitterBufferDepth": snapshot.jitter_buffer_depth,
+          }));
+        }
       }
       event = sync_con.next() => {
         match event {
@@ -426,32 +671,31 @@ async fn run_client(
               &connected,
               &mut ready_tx,
               &mut speaker_handlers,
+              &stats,
+              &mut reconnect_attempt,
             ).await;
           }
           Some(Err(e)) => {
-            if let Some(tx) = ready_tx.take() {
-              let _ = tx.send(Err(format!("Connection failed: {e}")));
+            if !try_reconnect(
+              &reconnect_policy, &mut reconnect_attempt, "stream_error", &format!("{e}"), true,
+              &conn_params, &identity,
+              &event_tsfn, &connected, &mut ready_tx, &mut control_rx, &mut sync_con,
+              &mut whisper_target, &mut master_volume, &mut client_volumes,
+            ).await {
+              return;
             }
-            connected.store(false, Ordering::SeqCst);
-            emit_error(&event_tsfn, "E_STREAM", &format!("{e}"));
-            emit(
-              &event_tsfn,
-              "disconnected",
-              serde_json::json!({ "temporary": false, "reason": "stream_error" }),
-            );
-            return;
+            continue 'connection;
           }
           None => {
-            if let Some(tx) = ready_tx.take() {
-              let _ = tx.send(Err("Disconnected before connected".to_string()));
+            if !try_reconnect(
+              &reconnect_policy, &mut reconnect_attempt, "eof", "Disconnected before connected", false,
+              &conn_params, &identity,
+              &event_tsfn, &connected, &mut ready_tx, &mut control_rx, &mut sync_con,
+              &mut whisper_target, &mut master_volume, &mut client_volumes,
+            ).await {
+              return;
             }
-            connected.store(false, Ordering::SeqCst);
-            emit(
-              &event_tsfn,
-              "disconnected",
-              serde_json::json!({ "temporary": false, "reason": "eof" }),
-            );
-            return;
+            continue 'connection;
           }
         }
       }
@@ -464,6 +708,167 @@ async fn run_client(
   }
 }
 
+/// Builds a fresh `Connection` from stored identity/options, for both the
+/// initial connect and every reconnect attempt.
+fn build_connection(params: &ConnectionParams, identity: Identity) -> Result<Connection, String> {
+  let mut builder = Connection::build(params.address.clone()).identity(identity);
+  if let Some(password) = params.password.clone() {
+    builder = builder.password(password);
+  }
+  if let Some(nick) = params.nickname.clone() {
+    builder = builder.name(nick);
+  }
+  if let Some(channel) = params.channel.clone() {
+    builder = builder.channel(channel);
+  }
+  if let Some(channel_password) = params.channel_password.clone() {
+    builder = builder.channel_password(channel_password);
+  }
+  match params.log_level.as_deref() {
+    Some("commands") => {
+      builder = builder.log_commands(true);
+    }
+    Some("packets") => {
+      builder = builder.log_commands(true).log_packets(true);
+    }
+    Some("udp") => {
+      builder = builder.log_commands(true).log_packets(true).log_udp_packets(true);
+    }
+    _ => {}
+  }
+  builder.connect().map_err(|e| format!("Failed to connect: {e}"))
+}
+
+/// On a non-graceful stream error, either rebuilds the connection in place
+/// per `policy` (retrying with backoff across repeated rebuild failures) and
+/// returns `true` to resume the select loop, or (with no policy, attempts
+/// exhausted, or a `Disconnect` arriving mid-backoff) emits a final
+/// `disconnected` and returns `false` so the caller tears the worker down.
+/// While waiting out a backoff delay, `control_rx` is still polled so a
+/// `Disconnect` request isn't stalled behind it. A queued `PushFrame` is
+/// dropped, since there's no live connection to send it on, but the
+/// whisper-target/volume setters don't need one either — they're applied to
+/// local state immediately so they've already taken effect once `sync_con` is
+/// rebuilt.
+#[allow(clippy::too_many_arguments)]
+async fn try_reconnect(
+  policy: &Option<ReconnectPolicy>,
+  reconnect_attempt: &mut u32,
+  reason: &str,
+  error_msg: &str,
+  is_error: bool,
+  params: &ConnectionParams,
+  identity: &Identity,
+  event_tsfn: &Arc<Mutex<Option<EventTsfn>>>,
+  connected: &Arc<AtomicBool>,
+  ready_tx: &mut Option<oneshot::Sender<Result<(), String>>>,
+  control_rx: &mut mpsc::Receiver<ControlMessage>,
+  sync_con: &mut SyncConnection,
+  whisper_target: &mut Option<WhisperTarget>,
+  master_volume: &mut f32,
+  client_volumes: &mut HashMap<ClientId, f32>,
+) -> bool {
+  connected.store(false, Ordering::SeqCst);
+  if is_error {
+    emit_error(event_tsfn, "E_STREAM", error_msg);
+  }
+
+  let Some(policy) = policy else {
+    if let Some(tx) = ready_tx.take() {
+      let _ = tx.send(Err(error_msg.to_string()));
+    }
+    emit(event_tsfn, "disconnected", serde_json::json!({ "temporary": false, "reason": reason }));
+    return false;
+  };
+
+  enum Interrupt {
+    Disconnect(oneshot::Sender<Result<(), String>>),
+    ChannelClosed,
+  }
+
+  loop {
+    if !policy.allows(*reconnect_attempt) {
+      if let Some(tx) = ready_tx.take() {
+        let _ = tx.send(Err(error_msg.to_string()));
+      }
+      emit(
+        event_tsfn,
+        "disconnected",
+        serde_json::json!({ "temporary": false, "reason": reason, "attemptsExhausted": true }),
+      );
+      return false;
+    }
+
+    *reconnect_attempt += 1;
+    let delay = policy.delay_for(*reconnect_attempt);
+    emit(
+      event_tsfn,
+      "reconnecting",
+      serde_json::json!({ "attempt": *reconnect_attempt, "delayMs": delay.as_millis() as u64 }),
+    );
+
+    // Race the backoff delay against the control channel. A queued `PushFrame`
+    // is dropped (there's no live connection to send it on), but the
+    // whisper-target/volume setters are applied to local state right away so
+    // they're already in effect once `sync_con` is rebuilt below; none of
+    // this cuts the delay short.
+    let interrupt = tokio::select! {
+      _ = time::sleep(delay) => None,
+      result = async {
+        loop {
+          match control_rx.recv().await {
+            Some(ControlMessage::Disconnect { message: _, done }) => break Interrupt::Disconnect(done),
+            Some(ControlMessage::SetWhisperTarget(target)) => {
+              *whisper_target = target;
+              continue;
+            }
+            Some(ControlMessage::SetMasterVolume(volume)) => {
+              *master_volume = volume;
+              continue;
+            }
+            Some(ControlMessage::SetClientVolume(client_id, volume)) => {
+              client_volumes.insert(client_id, volume);
+              continue;
+            }
+            Some(ControlMessage::PushFrame(_)) => continue,
+            None => break Interrupt::ChannelClosed,
+          }
+        }
+      } => Some(result),
+    };
+
+    match interrupt {
+      Some(Interrupt::Disconnect(done)) => {
+        let _ = done.send(Ok(()));
+        emit(
+          event_tsfn,
+          "disconnected",
+          serde_json::json!({ "temporary": false, "reason": "client_disconnect" }),
+        );
+        return false;
+      }
+      Some(Interrupt::ChannelClosed) => {
+        emit(
+          event_tsfn,
+          "disconnected",
+          serde_json::json!({ "temporary": false, "reason": "channel_closed" }),
+        );
+        return false;
+      }
+      None => {}
+    }
+
+    match build_connection(params, identity.clone()) {
+      Ok(con) => {
+        *sync_con = con.into();
+        return true;
+      }
+      Err(msg) => emit_error(event_tsfn, "E_CONNECT", &msg),
+    }
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn handle_stream_item(
   item: SyncStreamItem,
   sync_con: &mut SyncConnection,
@@ -471,6 +876,8 @@ async fn handle_stream_item(
   connected: &Arc<AtomicBool>,
   ready_tx: &mut Option<oneshot::Sender<Result<(), String>>>,
   speaker_handlers: &mut HashMap<ClientId, AudioHandler<ClientId>>,
+  stats: &Arc<Mutex<StatsCounters>>,
+  reconnect_attempt: &mut u32,
 ) {
   match item {
     SyncStreamItem::BookEvents(_) => {
@@ -483,15 +890,25 @@ async fn handle_stream_item(
           .ok()
           .map(|s| s.server.name.clone())
           .unwrap_or_default();
-        emit(
-          event_tsfn,
-          "connected",
-          serde_json::json!({ "serverName": server_name }),
-        );
+
+        if *reconnect_attempt > 0 {
+          emit(
+            event_tsfn,
+            "reconnected",
+            serde_json::json!({ "serverName": server_name, "attempt": *reconnect_attempt }),
+          );
+          *reconnect_attempt = 0;
+        } else {
+          emit(
+            event_tsfn,
+            "connected",
+            serde_json::json!({ "serverName": server_name }),
+          );
+        }
       }
     }
     SyncStreamItem::Audio(packet) => {
-      handle_incoming_audio(packet, speaker_handlers, event_tsfn);
+      handle_incoming_audio(packet, speaker_handlers, event_tsfn, stats);
     }
     SyncStreamItem::DisconnectedTemporarily(reason) => {
       connected.store(false, Ordering::SeqCst);
@@ -505,6 +922,75 @@ async fn handle_stream_item(
   }
 }
 
+/// Downmixes interleaved `i16` input to mono and resamples it to 48kHz,
+/// carrying a small history buffer and a fractional read position across
+/// `pushFrame` calls so chunk boundaries don't click.
+struct Resampler {
+  in_rate: usize,
+  channels: usize,
+  pos: f64,
+  history: VecDeque<f32>,
+}
+
+impl Resampler {
+  fn new(in_rate: usize, channels: usize) -> Self {
+    // A rate of 0 would make `process`'s resample step never advance `pos`,
+    // spinning forever; treat it as "already 48kHz" instead.
+    let in_rate = if in_rate == 0 { SAMPLE_RATE } else { in_rate };
+    Self { in_rate, channels: channels.max(1), pos: 0.0, history: VecDeque::new() }
+  }
+
+  fn process(&mut self, raw: &[i16]) -> Vec<f32> {
+    let mono: Vec<f32> = if self.channels <= 1 {
+      raw.iter().map(|s| *s as f32 / i16::MAX as f32).collect()
+    } else {
+      raw
+        .chunks(self.channels)
+        .map(|chunk| {
+          let sum: i32 = chunk.iter().map(|s| *s as i32).sum();
+          (sum as f32 / self.channels as f32) / i16::MAX as f32
+        })
+        .collect()
+    };
+
+    let mut buffer: Vec<f32> = self.history.drain(..).collect();
+    buffer.extend(mono);
+
+    if self.in_rate == SAMPLE_RATE {
+      return buffer;
+    }
+    if buffer.len() < 2 {
+      self.history.extend(buffer);
+      return Vec::new();
+    }
+
+    let ratio = self.in_rate as f64 / SAMPLE_RATE as f64;
+    let mut out = Vec::new();
+    while self.pos + 1.0 < buffer.len() as f64 {
+      let idx = self.pos.floor() as usize;
+      let frac = (self.pos - idx as f64) as f32;
+      out.push(buffer[idx] * (1.0 - frac) + buffer[idx + 1] * frac);
+      self.pos += ratio;
+    }
+
+    let consumed_whole = (self.pos.floor() as usize).min(buffer.len());
+    self.history.extend(buffer[consumed_whole..].iter().copied());
+    self.pos -= consumed_whole as f64;
+    out
+  }
+}
+
+fn build_encoder(profile: AudioProfile, bitrate: Option<i32>, vbr: Option<bool>) -> Result<Encoder, audiopus::Error> {
+  let encoder = Encoder::new(audiopus::SampleRate::Hz48000, audiopus::Channels::Mono, profile.application())?;
+  if let Some(bitrate) = bitrate {
+    encoder.set_bitrate(audiopus::Bitrate::BitsPerSecond(bitrate))?;
+  }
+  if let Some(vbr) = vbr {
+    encoder.set_vbr(vbr)?;
+  }
+  Ok(encoder)
+}
+
 fn parse_or_create_identity(input: Option<&str>) -> Result<Identity, String> {
   match input {
     Some(raw) => Identity::new_from_str(raw).map_err(|e| format!("Failed to parse identity: {e}")),
@@ -517,28 +1003,11 @@ fn identity_to_string(identity: &Identity) -> String {
   format!("{}V{}", identity.counter(), key_b64)
 }
 
-fn to_frames(samples: &[i16]) -> Vec<[i16; FRAME_SAMPLES]> {
-  if samples.is_empty() {
-    return Vec::new();
-  }
-
-  let mut frames = Vec::with_capacity((samples.len() + FRAME_SAMPLES - 1) / FRAME_SAMPLES);
-  let mut index = 0usize;
-  while index < samples.len() {
-    let mut frame = [0i16; FRAME_SAMPLES];
-    let end = (index + FRAME_SAMPLES).min(samples.len());
-    let count = end - index;
-    frame[..count].copy_from_slice(&samples[index..end]);
-    frames.push(frame);
-    index = end;
-  }
-  frames
-}
-
 fn handle_incoming_audio(
   packet: InAudioBuf,
   speaker_handlers: &mut HashMap<ClientId, AudioHandler<ClientId>>,
   event_tsfn: &Arc<Mutex<Option<EventTsfn>>>,
+  stats: &Mutex<StatsCounters>,
 ) {
   let from = match packet.data().data() {
     AudioData::S2C { from, .. } | AudioData::S2CWhisper { from, .. } => ClientId(*from),
@@ -549,24 +1018,35 @@ fn handle_incoming_audio(
     Ok(packet_copy) => {
       let handler = speaker_handlers.entry(from).or_default();
       if let Err(e) = handler.handle_packet(from, packet_copy) {
-        let msg = format!("{e}");
-        if should_report_decode_error(&msg) {
-          emit_error(event_tsfn, "E_AUDIO_DECODE", &msg);
-        }
-      }
-    }
-    Err(e) => {
-      let msg = format!("{e}");
-      if should_report_decode_error(&msg) {
-        emit_error(event_tsfn, "E_AUDIO_DECODE", &msg);
+        report_decode_drop(&format!("{e}"), event_tsfn, stats);
       }
     }
+    Err(e) => report_decode_drop(&format!("{e}"), event_tsfn, stats),
+  }
+}
+
+/// Classifies a decode failure into one of the two cases the underlying
+/// jitter buffer currently filters out (a packet arriving too late, or the
+/// queue already being full), counting it either way and only surfacing an
+/// `error` event for failures that aren't just expected packet loss.
+fn report_decode_drop(msg: &str, event_tsfn: &Arc<Mutex<Option<EventTsfn>>>, stats: &Mutex<StatsCounters>) {
+  let mut stats = stats.lock().expect("stats mutex poisoned");
+  if msg.contains("too late") {
+    stats.decode_drops_late += 1;
+  } else if msg.contains("queue is full") {
+    stats.decode_drops_full_queue += 1;
+  } else {
+    drop(stats);
+    emit_error(event_tsfn, "E_AUDIO_DECODE", msg);
   }
 }
 
 fn emit_audio_frames(
   event_tsfn: &Arc<Mutex<Option<EventTsfn>>>,
   speaker_handlers: &mut HashMap<ClientId, AudioHandler<ClientId>>,
+  master_volume: f32,
+  client_volumes: &HashMap<ClientId, f32>,
+  limiter: &mut SoftLimiter,
 ) {
   let mut to_remove = Vec::new();
   let mut mixed = vec![0.0f32; FRAME_SAMPLES];
@@ -580,11 +1060,16 @@ fn emit_audio_frames(
       to_remove.push(*client_id);
     }
 
-    let frame = downmix_stereo_to_mono(&frame_stereo);
+    let mut frame = downmix_stereo_to_mono(&frame_stereo);
     if !has_audio(&frame) {
       continue;
     }
 
+    let gain = master_volume * client_volumes.get(client_id).copied().unwrap_or(1.0);
+    for sample in frame.iter_mut() {
+      *sample *= gain;
+    }
+
     for (m, s) in mixed.iter_mut().zip(frame.iter()) {
       *m += *s;
     }
@@ -592,6 +1077,8 @@ fn emit_audio_frames(
     emit_audio_payload(event_tsfn, "audioSpeaker", Some(client_id.0), &frame);
   }
 
+  limiter.apply(&mut mixed);
+
   // Emit a fixed-rate mixed frame every tick to preserve a stable timeline.
   emit_audio_payload(event_tsfn, "audioMixed", None, &mixed);
 
@@ -600,6 +1087,32 @@ fn emit_audio_frames(
   }
 }
 
+/// A look-ahead-free soft limiter: tracks a smoothed gain that snaps down
+/// quickly when the mixed frame threatens to clip (fast attack) and eases
+/// back up slowly once the signal is quiet again (slow release), so mixing
+/// several loud speakers together stays musical instead of hard-clipping.
+struct SoftLimiter {
+  gain: f32,
+}
+
+const LIMITER_THRESHOLD: f32 = 0.95;
+const LIMITER_ATTACK: f32 = 0.01;
+const LIMITER_RELEASE: f32 = 0.0001;
+
+impl SoftLimiter {
+  fn new() -> Self { Self { gain: 1.0 } }
+
+  fn apply(&mut self, frame: &mut [f32]) {
+    for sample in frame.iter_mut() {
+      let peak = sample.abs() * self.gain;
+      let target_gain = if peak > LIMITER_THRESHOLD { LIMITER_THRESHOLD / sample.abs().max(1e-9) } else { 1.0 };
+      let coeff = if target_gain < self.gain { LIMITER_ATTACK } else { LIMITER_RELEASE };
+      self.gain += (target_gain - self.gain) * coeff;
+      *sample = (*sample * self.gain).clamp(-1.0, 1.0);
+    }
+  }
+}
+
 fn downmix_stereo_to_mono(input: &[f32]) -> Vec<f32> {
   if input.is_empty() {
     return vec![0.0; FRAME_SAMPLES];
@@ -676,10 +1189,6 @@ fn emit_error(event_tsfn: &Arc<Mutex<Option<EventTsfn>>>, code: &str, message: &
   );
 }
 
-fn should_report_decode_error(msg: &str) -> bool {
-  !(msg.contains("too late") || msg.contains("queue is full"))
-}
-
 #[napi(js_name = "decodeBase64PcmToBuffer")]
 pub fn decode_base64_pcm_to_buffer(b64: String) -> napi::Result<Buffer> {
   let bytes = base64::engine::general_purpose::STANDARD
@@ -1,20 +1,28 @@
-//! This example connects to a TeamSpeak server and sends audio from an mp3 file.
-//! It does not access any system audio input/output device.
-//! The file `examples/sample/music1.mp3` is played for 5 seconds, then the client disconnects.
+//! This example connects to a TeamSpeak server and sends audio from a local
+//! file. It does not access any system audio input/output device.
+//! By default `examples/sample/music1.mp3` is played for 5 seconds, then the
+//! client disconnects; pass `--input` to play an mp3, Ogg Vorbis or Ogg Opus
+//! file instead.
 
 use std::fs::File;
 use std::io::BufReader;
-use std::path::Path;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, anyhow, bail};
 use audiopus::coder::Encoder;
 use clap::Parser;
 use futures::prelude::*;
-use minimp3::{Decoder, Error as Mp3Error, Frame};
+use ogg::reading::PacketReader;
 use tokio::time::{self, Duration, MissedTickBehavior};
 use tsclientlib::{Connection, DisconnectOptions, Identity, StreamItem};
 use tsproto_packets::packets::{AudioData, CodecType, OutAudio, OutPacket};
 
+#[path = "common/mod.rs"]
+mod common;
+use common::audio_decode::{AudioDecoder, open_decoder};
+use common::sample_source::{RawFormat, Reader, SampleSource};
+
 const TARGET_SAMPLE_RATE: usize = 48_000;
 const FRAME_SAMPLES: usize = TARGET_SAMPLE_RATE / 50;
 const PLAY_SECONDS: usize = 5;
@@ -29,9 +37,59 @@ struct Args {
 	/// The password
 	#[arg(short, long, default_value = "")]
 	password: String,
-	/// Volume multiplier for the mp3 audio
+	/// Audio file to play: .mp3, .ogg (Vorbis) or .opus (Ogg Opus)
+	#[arg(short, long)]
+	input: Option<PathBuf>,
+	/// Start playback this many milliseconds into the file
+	#[arg(long, default_value_t = 0)]
+	seek_ms: i64,
+	/// Volume multiplier for the audio
 	#[arg(default_value_t = 1.0)]
 	volume: f32,
+	/// Send raw Opus packets straight from an Ogg Opus file instead of
+	/// decoding and re-encoding. Requires a 48kHz Ogg Opus `--input` and
+	/// is incompatible with `--volume` (scaling an encoded packet isn't
+	/// possible), which falls back to the decoding path if non-default.
+	#[arg(long, default_value_t = false)]
+	passthrough: bool,
+	/// Read raw PCM from a TCP "radio" feed (host:port) instead of a local
+	/// file. Overrides `--input`/`--passthrough`.
+	#[arg(long)]
+	tcp_source: Option<SocketAddr>,
+	/// Wire format of samples from `--tcp-source`
+	#[arg(long, value_enum, default_value_t = RawFormatArg::F32)]
+	raw_format: RawFormatArg,
+	/// Channel count of the `--tcp-source` stream
+	#[arg(long, default_value_t = 2)]
+	raw_channels: usize,
+	/// Sample rate of the `--tcp-source` stream
+	#[arg(long, default_value_t = 48_000)]
+	raw_sample_rate: usize,
+	/// Hex-encoded repeating XOR key to de-obfuscate the `--tcp-source` feed
+	#[arg(long)]
+	xor_key: Option<String>,
+	/// Opus codec to tag outgoing packets with; voice is tuned for speech,
+	/// music preserves wideband content better. Ignored by `--passthrough`,
+	/// which always tags packets as music
+	#[arg(long, value_enum, default_value_t = CodecArg::Voice)]
+	codec: CodecArg,
+	/// Opus encoder tuning; mirrors `--codec` by default
+	#[arg(long, value_enum)]
+	application: Option<ApplicationArg>,
+	/// Target bitrate in bits/s for the Opus encoder (encoder default if unset)
+	#[arg(long)]
+	bitrate: Option<i32>,
+	/// Opus encoder complexity, 0 (fastest) to 10 (best quality)
+	#[arg(long)]
+	complexity: Option<u8>,
+	/// Whisper to only these client ids instead of broadcasting to the
+	/// whole channel; comma-separated
+	#[arg(long, value_delimiter = ',')]
+	whisper_clients: Vec<u16>,
+	/// Whisper to only these channel ids instead of broadcasting to the
+	/// whole channel; comma-separated
+	#[arg(long, value_delimiter = ',')]
+	whisper_channels: Vec<u64>,
 	/// Print the content of all packets
 	///
 	/// 0. Print nothing
@@ -42,6 +100,125 @@ struct Args {
 	verbose: u8,
 }
 
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum RawFormatArg {
+	F32,
+	I16,
+}
+
+impl From<RawFormatArg> for RawFormat {
+	fn from(value: RawFormatArg) -> Self {
+		match value {
+			RawFormatArg::F32 => RawFormat::F32Le,
+			RawFormatArg::I16 => RawFormat::I16Le,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum CodecArg {
+	Voice,
+	Music,
+}
+
+impl From<CodecArg> for CodecType {
+	fn from(value: CodecArg) -> Self {
+		match value {
+			CodecArg::Voice => CodecType::OpusVoice,
+			CodecArg::Music => CodecType::OpusMusic,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum ApplicationArg {
+	Voice,
+	Audio,
+	LowDelay,
+}
+
+impl From<ApplicationArg> for audiopus::Application {
+	fn from(value: ApplicationArg) -> Self {
+		match value {
+			ApplicationArg::Voice => audiopus::Application::Voip,
+			ApplicationArg::Audio => audiopus::Application::Audio,
+			ApplicationArg::LowDelay => audiopus::Application::LowDelay,
+		}
+	}
+}
+
+impl CodecArg {
+	/// The `Application` tuning that matches this codec unless overridden.
+	fn default_application(self) -> audiopus::Application {
+		match self {
+			CodecArg::Voice => audiopus::Application::Voip,
+			CodecArg::Music => audiopus::Application::Audio,
+		}
+	}
+}
+
+/// Opus encoder settings derived from the CLI flags, applied after
+/// construction via the `audiopus` encoder setters.
+struct EncoderConfig {
+	application: audiopus::Application,
+	bitrate: Option<i32>,
+	complexity: Option<u8>,
+}
+
+impl EncoderConfig {
+	fn from_args(args: &Args) -> Self {
+		Self {
+			application: args.application.map(Into::into).unwrap_or_else(|| args.codec.default_application()),
+			bitrate: args.bitrate,
+			complexity: args.complexity,
+		}
+	}
+
+	fn build_encoder(&self, channels: audiopus::Channels) -> Result<Encoder> {
+		let encoder = Encoder::new(audiopus::SampleRate::Hz48000, channels, self.application)
+			.context("Failed to create opus encoder")?;
+		if let Some(bitrate) = self.bitrate {
+			encoder
+				.set_bitrate(audiopus::Bitrate::BitsPerSecond(bitrate))
+				.context("Failed to set opus bitrate")?;
+		}
+		if let Some(complexity) = self.complexity {
+			encoder.set_complexity(complexity).context("Failed to set opus complexity")?;
+		}
+		Ok(encoder)
+	}
+}
+
+/// Who to send outgoing audio to: the whole channel (a normal `C2S` packet)
+/// or a specific set of clients/channels (a `C2SWhisper` packet).
+enum WhisperTarget {
+	Channel,
+	Whisper { clients: Vec<u16>, channels: Vec<u64> },
+}
+
+impl WhisperTarget {
+	fn from_args(args: &Args) -> Self {
+		if args.whisper_clients.is_empty() && args.whisper_channels.is_empty() {
+			WhisperTarget::Channel
+		} else {
+			WhisperTarget::Whisper { clients: args.whisper_clients.clone(), channels: args.whisper_channels.clone() }
+		}
+	}
+
+	fn wrap<'a>(&'a self, codec: CodecType, data: &'a [u8]) -> OutPacket {
+		match self {
+			WhisperTarget::Channel => OutAudio::new(&AudioData::C2S { id: 0, codec, data }),
+			WhisperTarget::Whisper { clients, channels } => OutAudio::new(&AudioData::C2SWhisper {
+				id: 0,
+				codec,
+				channels,
+				clients,
+				data,
+			}),
+		}
+	}
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
 	real_main().await
@@ -51,11 +228,31 @@ async fn real_main() -> Result<()> {
 	tracing_subscriber::fmt::init();
 	let args = Args::parse();
 
-	let audio_path =
-		Path::new(env!("CARGO_MANIFEST_DIR")).join("examples").join("sample").join("music1.mp3");
-	let pcm = decode_mp3_to_target_pcm(&audio_path, PLAY_SECONDS)
-		.with_context(|| format!("Failed to decode {}", audio_path.display()))?;
-	let packets = encode_packets(&pcm, args.volume)?;
+	let whisper_target = WhisperTarget::from_args(&args);
+	let encoder_config = EncoderConfig::from_args(&args);
+	let codec: CodecType = args.codec.into();
+
+	let packets = if args.tcp_source.is_none() {
+		let audio_path = args.input.clone().unwrap_or_else(|| {
+			Path::new(env!("CARGO_MANIFEST_DIR")).join("examples").join("sample").join("music1.mp3")
+		});
+
+		let use_passthrough = args.passthrough && args.volume == 1.0;
+		if args.passthrough && !use_passthrough {
+			tracing::warn!("--volume cannot be applied on the Opus passthrough path; decoding instead");
+		}
+
+		Some(if use_passthrough {
+			passthrough_ogg_opus_packets(&audio_path, PLAY_SECONDS * 50, &whisper_target)
+				.with_context(|| format!("Failed to passthrough {}", audio_path.display()))?
+		} else {
+			let pcm = decode_to_target_pcm(&audio_path, args.seek_ms, PLAY_SECONDS)
+				.with_context(|| format!("Failed to decode {}", audio_path.display()))?;
+			encode_packets(&pcm, args.volume, codec, &encoder_config, &whisper_target)?
+		})
+	} else {
+		None
+	};
 
 	let con_config = Connection::build(args.address)
 		.log_commands(args.verbose >= 1)
@@ -83,19 +280,50 @@ async fn real_main() -> Result<()> {
 
 	let mut ticker = time::interval(Duration::from_millis(20));
 	ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
-	let mut packets = packets.into_iter();
-
-	loop {
-		tokio::select! {
-			_ = ticker.tick() => {
-				if let Some(packet) = packets.next() {
-					con.send_audio(packet)?;
-				} else {
+
+	if let Some(packets) = packets {
+		let mut packets = packets.into_iter();
+		loop {
+			tokio::select! {
+				_ = ticker.tick() => {
+					if let Some(packet) = packets.next() {
+						con.send_audio(packet)?;
+					} else {
+						break;
+					}
+				}
+				_ = tokio::signal::ctrl_c() => {
 					break;
 				}
 			}
-			_ = tokio::signal::ctrl_c() => {
-				break;
+		}
+	} else {
+		let addr = args.tcp_source.expect("checked above");
+		let mut reader = Reader::connect_tcp(addr).await?;
+		if let Some(hex_key) = &args.xor_key {
+			reader = reader.xor(parse_hex_key(hex_key)?);
+		}
+		let raw_frame_samples = FRAME_SAMPLES * args.raw_sample_rate / TARGET_SAMPLE_RATE;
+		let mut source =
+			SampleSource::spawn(reader, args.raw_format.into(), args.raw_channels, raw_frame_samples, 10);
+		let encoder = encoder_config.build_encoder(audiopus::Channels::Mono)?;
+		let mut opus_output = [0; MAX_OPUS_FRAME_SIZE];
+
+		for _ in 0..(PLAY_SECONDS * 50) {
+			tokio::select! {
+				_ = ticker.tick() => {
+					let raw = source.next_frame_or_silence(raw_frame_samples);
+					let resampled = resample_linear(&raw, args.raw_sample_rate, TARGET_SAMPLE_RATE);
+					let mut frame = [0.0f32; FRAME_SAMPLES];
+					for (dst, src) in frame.iter_mut().zip(resampled.iter()) {
+						*dst = *src * args.volume;
+					}
+					let len = encoder.encode_float(&frame, &mut opus_output).context("Failed to encode opus frame")?;
+					con.send_audio(whisper_target.wrap(codec, &opus_output[..len]))?;
+				}
+				_ = tokio::signal::ctrl_c() => {
+					break;
+				}
 			}
 		}
 	}
@@ -105,20 +333,29 @@ async fn real_main() -> Result<()> {
 	Ok(())
 }
 
-fn decode_mp3_to_target_pcm(path: &Path, play_seconds: usize) -> Result<Vec<f32>> {
-	let file = File::open(path)?;
-	let mut decoder = Decoder::new(BufReader::new(file));
+fn parse_hex_key(hex_key: &str) -> Result<Vec<u8>> {
+	if hex_key.len() % 2 != 0 {
+		bail!("--xor-key must have an even number of hex digits");
+	}
+	(0..hex_key.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(&hex_key[i..i + 2], 16).map_err(|e| anyhow!("Invalid --xor-key: {e}")))
+		.collect()
+}
+
+fn decode_to_target_pcm(path: &Path, seek_ms: i64, play_seconds: usize) -> Result<Vec<f32>> {
+	let mut decoder = open_decoder(path)?;
+	if seek_ms > 0 {
+		decoder.seek(seek_ms).with_context(|| format!("Failed to seek to {seek_ms}ms"))?;
+	}
+
 	let target_samples = TARGET_SAMPLE_RATE * play_seconds;
 	let mut out = Vec::with_capacity(target_samples);
 
 	while out.len() < target_samples {
-		let frame = match decoder.next_frame() {
-			Ok(frame) => frame,
-			Err(Mp3Error::Eof) => break,
-			Err(error) => return Err(anyhow!("mp3 decode error: {error}")),
-		};
-		let mono = frame_to_mono_f32(&frame);
-		let resampled = resample_linear(&mono, frame.sample_rate as usize, TARGET_SAMPLE_RATE);
+		let Some(frame) = decoder.next_frame()? else { break };
+		let mono = downmix_to_mono(&frame, decoder.channels());
+		let resampled = resample_linear(&mono, decoder.sample_rate(), TARGET_SAMPLE_RATE);
 		let remain = target_samples - out.len();
 		if resampled.len() > remain {
 			out.extend_from_slice(&resampled[..remain]);
@@ -136,20 +373,12 @@ fn decode_mp3_to_target_pcm(path: &Path, play_seconds: usize) -> Result<Vec<f32>
 	Ok(out)
 }
 
-fn frame_to_mono_f32(frame: &Frame) -> Vec<f32> {
-	let channels = frame.channels as usize;
+fn downmix_to_mono(frame: &[f32], channels: usize) -> Vec<f32> {
 	if channels <= 1 {
-		return frame.data.iter().map(|s| *s as f32 / 32768.0).collect();
+		return frame.to_vec();
 	}
 
-	frame
-		.data
-		.chunks(channels)
-		.map(|chunk| {
-			let sum: i32 = chunk.iter().map(|s| *s as i32).sum();
-			(sum as f32 / channels as f32) / 32768.0
-		})
-		.collect()
+	frame.chunks(channels).map(|chunk| chunk.iter().sum::<f32>() / channels as f32).collect()
 }
 
 fn resample_linear(input: &[f32], src_rate: usize, dst_rate: usize) -> Vec<f32> {
@@ -181,13 +410,49 @@ fn resample_linear(input: &[f32], src_rate: usize, dst_rate: usize) -> Vec<f32>
 	out
 }
 
-fn encode_packets(samples: &[f32], volume: f32) -> Result<Vec<OutPacket>> {
-	let encoder = Encoder::new(
-		audiopus::SampleRate::Hz48000,
-		audiopus::Channels::Mono,
-		audiopus::Application::Audio,
-	)
-	.context("Failed to create opus encoder")?;
+/// Reads raw Opus packets straight out of an Ogg Opus file and wraps each
+/// one into an outgoing audio packet for `target` (`C2S`, or `C2SWhisper`
+/// when whispering), without ever decoding to PCM or calling
+/// the `audiopus` encoder. Each Ogg packet already corresponds to one 20ms
+/// Opus frame, so this maps 1:1 onto the send ticker.
+fn passthrough_ogg_opus_packets(path: &Path, max_packets: usize, target: &WhisperTarget) -> Result<Vec<OutPacket>> {
+	let file = File::open(path)?;
+	let mut reader = PacketReader::new(BufReader::new(file));
+
+	let header = reader
+		.read_packet()
+		.context("Failed to read OpusHead packet")?
+		.ok_or_else(|| anyhow!("Empty Ogg Opus file"))?;
+	if !header.data.starts_with(b"OpusHead") {
+		bail!("{} is not an Ogg Opus stream", path.display());
+	}
+	let input_sample_rate = u32::from_le_bytes([header.data[12], header.data[13], header.data[14], header.data[15]]);
+	if input_sample_rate != 0 && input_sample_rate != TARGET_SAMPLE_RATE as u32 {
+		bail!(
+			"{} was encoded at {input_sample_rate}Hz, but passthrough requires 48kHz Opus",
+			path.display()
+		);
+	}
+
+	// Skip the OpusTags comment header packet.
+	reader.read_packet().context("Failed to read OpusTags packet")?;
+
+	let mut packets = Vec::new();
+	while packets.len() < max_packets {
+		let Some(packet) = reader.read_packet().context("Failed to read Ogg Opus packet")? else { break };
+		packets.push(target.wrap(CodecType::OpusMusic, &packet.data));
+	}
+	Ok(packets)
+}
+
+fn encode_packets(
+	samples: &[f32],
+	volume: f32,
+	codec: CodecType,
+	encoder_config: &EncoderConfig,
+	target: &WhisperTarget,
+) -> Result<Vec<OutPacket>> {
+	let encoder = encoder_config.build_encoder(audiopus::Channels::Mono)?;
 	let mut opus_output = [0; MAX_OPUS_FRAME_SIZE];
 	let mut packets = Vec::new();
 
@@ -199,12 +464,7 @@ fn encode_packets(samples: &[f32], volume: f32) -> Result<Vec<OutPacket>> {
 		let len = encoder
 			.encode_float(&frame, &mut opus_output)
 			.context("Failed to encode opus frame")?;
-		let packet = OutAudio::new(&AudioData::C2S {
-			id: 0,
-			codec: CodecType::OpusVoice,
-			data: &opus_output[..len],
-		});
-		packets.push(packet);
+		packets.push(target.wrap(codec, &opus_output[..len]));
 	}
 	Ok(packets)
 }
@@ -0,0 +1,306 @@
+//! This example turns the file-player demo into an actual music bot: instead
+//! of pre-buffering a fixed few seconds of audio, it decodes on demand into a
+//! bounded ring buffer while the 20ms send ticker drains it one frame at a
+//! time, so tracks of arbitrary length play with bounded memory. Several
+//! files can be queued as a `Playlist` that advances automatically on EOF,
+//! starts decoding the next track before the current one's buffer drains
+//! (gapless transition), and can be skipped, paused or stopped at runtime
+//! from stdin (`n`ext, `p`ause, `s`top).
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use audiopus::coder::Encoder;
+use clap::Parser;
+use futures::prelude::*;
+use tokio::io::{AsyncBufReadExt, BufReader as AsyncBufReader};
+use tokio::sync::mpsc;
+use tokio::time::{self, Duration, MissedTickBehavior};
+use tsclientlib::{Connection, DisconnectOptions, Identity, StreamItem};
+use tsproto_packets::packets::{AudioData, CodecType, OutAudio};
+
+#[path = "common/mod.rs"]
+mod common;
+use common::audio_decode::open_decoder;
+
+const TARGET_SAMPLE_RATE: usize = 48_000;
+const FRAME_SAMPLES: usize = TARGET_SAMPLE_RATE / 50;
+const MAX_OPUS_FRAME_SIZE: usize = 1275;
+/// How far ahead of playback the background decoder is allowed to run.
+const RING_BUFFER_SAMPLES: usize = TARGET_SAMPLE_RATE * 2;
+/// Start decoding the next track once the current one has this many samples
+/// left, so the transition between tracks has no silence gap.
+const GAPLESS_LOOKAHEAD_SAMPLES: usize = TARGET_SAMPLE_RATE / 2;
+
+#[derive(Parser, Debug)]
+#[command(author, about)]
+struct Args {
+	/// The address of the server to connect to
+	#[arg(short, long, default_value = "localhost")]
+	address: String,
+	/// The password
+	#[arg(short, long, default_value = "")]
+	password: String,
+	/// Tracks to queue and play in order (.mp3, .ogg, .opus)
+	#[arg(required = true)]
+	tracks: Vec<PathBuf>,
+	/// Print the content of all packets
+	///
+	/// 0. Print nothing
+	/// 1. Print command string
+	/// 2. Print packets
+	/// 3. Print udp packets
+	#[arg(short, long, action = clap::ArgAction::Count)]
+	verbose: u8,
+}
+
+enum Control {
+	Skip,
+	TogglePause,
+	Stop,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> { real_main().await }
+
+async fn real_main() -> Result<()> {
+	tracing_subscriber::fmt::init();
+	let args = Args::parse();
+
+	let con_config = Connection::build(args.address)
+		.log_commands(args.verbose >= 1)
+		.log_packets(args.verbose >= 2)
+		.log_udp_packets(args.verbose >= 3)
+		.password(args.password);
+
+	let id = Identity::new_from_str(
+		"MG0DAgeAAgEgAiAIXJBlj1hQbaH0Eq0DuLlCmH8bl+veTAO2+\
+		k9EQjEYSgIgNnImcmKo7ls5mExb6skfK2Tw+u54aeDr0OP1ITs\
+		C/50CIA8M5nmDBnmDM/gZ//4AAAAAAAAAAAAAAAAAAAAZRzOI",
+	)
+	.unwrap();
+	let con_config = con_config.identity(id);
+
+	let mut con = con_config.connect()?;
+	let r = con
+		.events()
+		.try_filter(|e| future::ready(matches!(e, StreamItem::BookEvents(_))))
+		.next()
+		.await;
+	if let Some(r) = r {
+		r?;
+	}
+
+	let (control_tx, mut control_rx) = mpsc::channel(8);
+	tokio::spawn(read_stdin_controls(control_tx));
+
+	let mut playlist = Playlist::new(args.tracks);
+	let mut current = playlist.next_track()?;
+
+	let encoder = Encoder::new(
+		audiopus::SampleRate::Hz48000,
+		audiopus::Channels::Mono,
+		audiopus::Application::Audio,
+	)
+	.context("Failed to create opus encoder")?;
+	let mut opus_output = [0; MAX_OPUS_FRAME_SIZE];
+
+	let mut ticker = time::interval(Duration::from_millis(20));
+	ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+	let mut paused = false;
+
+	loop {
+		let Some(track) = current.as_mut() else { break };
+
+		tokio::select! {
+			ctrl = control_rx.recv() => {
+				match ctrl {
+					Some(Control::Skip) => current = playlist.next_track()?,
+					Some(Control::TogglePause) => paused = !paused,
+					Some(Control::Stop) | None => break,
+				}
+			}
+			_ = ticker.tick() => {
+				if paused {
+					continue;
+				}
+
+				if track.remaining_in_buffer() < GAPLESS_LOOKAHEAD_SAMPLES {
+					playlist.prefetch_next()?;
+				}
+
+				let frame = track.pop_frame();
+				let Some(frame) = frame else {
+					current = playlist.next_track()?;
+					continue;
+				};
+				let len = encoder.encode_float(&frame, &mut opus_output).context("Failed to encode opus frame")?;
+				let packet = OutAudio::new(&AudioData::C2S {
+					id: 0,
+					codec: CodecType::OpusMusic,
+					data: &opus_output[..len],
+				});
+				con.send_audio(packet)?;
+			}
+			_ = tokio::signal::ctrl_c() => {
+				break;
+			}
+		}
+	}
+
+	con.disconnect(DisconnectOptions::new())?;
+	con.events().for_each(|_| future::ready(())).await;
+	Ok(())
+}
+
+async fn read_stdin_controls(control_tx: mpsc::Sender<Control>) {
+	let mut lines = AsyncBufReader::new(tokio::io::stdin()).lines();
+	while let Ok(Some(line)) = lines.next_line().await {
+		let control = match line.trim() {
+			"n" => Control::Skip,
+			"p" => Control::TogglePause,
+			"s" => Control::Stop,
+			_ => continue,
+		};
+		if control_tx.send(control).await.is_err() {
+			break;
+		}
+	}
+}
+
+/// A queue of track paths plus at most one prefetched `Track`, so the next
+/// one can start streaming before the current one's buffer runs dry.
+struct Playlist {
+	queue: VecDeque<PathBuf>,
+	prefetched: Option<Track>,
+}
+
+impl Playlist {
+	fn new(tracks: Vec<PathBuf>) -> Self { Self { queue: tracks.into(), prefetched: None } }
+
+	/// Returns the next track to play, using a prefetched one if available.
+	fn next_track(&mut self) -> Result<Option<Track>> {
+		if let Some(track) = self.prefetched.take() {
+			return Ok(Some(track));
+		}
+		self.start_next()
+	}
+
+	/// Begins streaming the next queued track in the background if one
+	/// isn't already prefetched.
+	fn prefetch_next(&mut self) -> Result<()> {
+		if self.prefetched.is_some() {
+			return Ok(());
+		}
+		self.prefetched = self.start_next()?;
+		Ok(())
+	}
+
+	fn start_next(&mut self) -> Result<Option<Track>> {
+		let Some(path) = self.queue.pop_front() else { return Ok(None) };
+		Ok(Some(Track::spawn(path)))
+	}
+}
+
+/// One track's streaming decode pipeline: a background task decodes and
+/// resamples into a bounded ring buffer, fed to the caller one frame at a
+/// time via `pop_frame`.
+struct Track {
+	sample_rx: mpsc::Receiver<f32>,
+	buffered: VecDeque<f32>,
+}
+
+impl Track {
+	fn spawn(path: PathBuf) -> Self {
+		let (sample_tx, sample_rx) = mpsc::channel(RING_BUFFER_SAMPLES);
+		tokio::task::spawn_blocking(move || {
+			if let Err(error) = decode_track(&path, &sample_tx) {
+				tracing::warn!(%error, path = %path.display(), "Failed to decode track");
+			}
+		});
+		Self { sample_rx, buffered: VecDeque::new() }
+	}
+
+	/// Pops one `FRAME_SAMPLES` mono frame, or `None` once the track's
+	/// decoder has finished and the buffer has drained.
+	fn pop_frame(&mut self) -> Option<[f32; FRAME_SAMPLES]> {
+		while self.buffered.len() < FRAME_SAMPLES {
+			match self.sample_rx.try_recv() {
+				Ok(sample) => self.buffered.push_back(sample),
+				// The decoder is still running but hasn't caught up: pad with
+				// silence rather than treating the stall as end-of-track.
+				Err(mpsc::error::TryRecvError::Empty) => break,
+				Err(mpsc::error::TryRecvError::Disconnected) => {
+					if self.buffered.is_empty() {
+						return None;
+					}
+					break;
+				}
+			}
+		}
+
+		let mut frame = [0.0f32; FRAME_SAMPLES];
+		for slot in frame.iter_mut() {
+			*slot = self.buffered.pop_front().unwrap_or(0.0);
+		}
+		Some(frame)
+	}
+
+	/// Total undrained samples: the small post-decode staging buffer plus
+	/// whatever the background decoder has already queued in `sample_rx`, so
+	/// the gapless lookahead check fires near end-of-track rather than as
+	/// soon as `buffered` itself runs low (which happens almost immediately,
+	/// since `pop_frame` only ever tops it up to one frame at a time). The
+	/// check also fires at the start of a track, before the decoder has
+	/// filled `sample_rx`, so the next track is usually prefetched early.
+	fn remaining_in_buffer(&self) -> usize { self.buffered.len() + self.sample_rx.len() }
+}
+
+fn decode_track(path: &std::path::Path, sample_tx: &mpsc::Sender<f32>) -> Result<()> {
+	let mut decoder = open_decoder(path)?;
+	while let Some(frame) = decoder.next_frame()? {
+		let channels = decoder.channels().max(1);
+		let mono: Vec<f32> = if channels <= 1 {
+			frame
+		} else {
+			frame.chunks(channels).map(|chunk| chunk.iter().sum::<f32>() / channels as f32).collect()
+		};
+		let resampled = resample_linear(&mono, decoder.sample_rate(), TARGET_SAMPLE_RATE);
+		for sample in resampled {
+			if sample_tx.blocking_send(sample).is_err() {
+				return Ok(());
+			}
+		}
+	}
+	Ok(())
+}
+
+fn resample_linear(input: &[f32], src_rate: usize, dst_rate: usize) -> Vec<f32> {
+	if input.is_empty() {
+		return Vec::new();
+	}
+	if src_rate == dst_rate {
+		return input.to_vec();
+	}
+	if input.len() == 1 {
+		return vec![input[0]];
+	}
+
+	let out_len =
+		(((input.len() as u64) * (dst_rate as u64) + (src_rate as u64) - 1) / (src_rate as u64)) as usize;
+	let mut out = Vec::with_capacity(out_len);
+
+	for i in 0..out_len {
+		let pos = (i as f64) * (src_rate as f64) / (dst_rate as f64);
+		let idx = pos.floor() as usize;
+		let frac = (pos - idx as f64) as f32;
+		if idx + 1 < input.len() {
+			out.push(input[idx] * (1.0 - frac) + input[idx + 1] * frac);
+		} else {
+			out.push(*input.last().unwrap());
+		}
+	}
+
+	out
+}
@@ -0,0 +1,228 @@
+//! This example bridges audio from an external per-speaker PCM source (e.g. a
+//! Discord voice connection delivering decoded 48 kHz stereo PCM keyed by
+//! speaker SSRC) into a single outgoing TeamSpeak Opus stream.
+//!
+//! It does not speak the Discord voice protocol itself: each source simply
+//! connects over a local TCP socket and streams raw `f32` little-endian PCM
+//! frames, which is enough to demonstrate and test the mixing path without
+//! pulling in a full Discord client. Wiring a real Discord voice receiver in
+//! just means calling `MixingBuffer::push` from its per-SSRC decode callback
+//! instead of the `read_source` task below.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+
+use anyhow::{Context, Result};
+use audiopus::coder::Encoder;
+use clap::Parser;
+use futures::prelude::*;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::{self, Duration, MissedTickBehavior};
+use tsclientlib::{Connection, DisconnectOptions, Identity, StreamItem};
+use tsproto_packets::packets::{AudioData, CodecType, OutAudio, OutPacket};
+
+const TARGET_SAMPLE_RATE: usize = 48_000;
+const FRAME_SAMPLES: usize = TARGET_SAMPLE_RATE / 50;
+const CHANNELS: usize = 2;
+const MAX_OPUS_FRAME_SIZE: usize = 1275;
+/// How much buffered audio a single source may accumulate before the oldest
+/// samples are dropped, to stop a fast producer from building up latency.
+const MAX_BUFFERED_MS: usize = 400;
+const MAX_BUFFERED_SAMPLES: usize = TARGET_SAMPLE_RATE * CHANNELS * MAX_BUFFERED_MS / 1000;
+
+/// Identifies one inbound audio source, e.g. a Discord speaker's SSRC.
+pub type SourceId = u32;
+
+#[derive(Parser, Debug)]
+#[command(author, about)]
+struct Args {
+	/// The address of the TeamSpeak server to connect to
+	#[arg(short, long, default_value = "localhost")]
+	address: String,
+	/// The password
+	#[arg(short, long, default_value = "")]
+	password: String,
+	/// Local address to accept per-source PCM streams on
+	#[arg(short, long, default_value = "127.0.0.1:4050")]
+	listen: SocketAddr,
+	/// Print the content of all packets
+	///
+	/// 0. Print nothing
+	/// 1. Print command string
+	/// 2. Print packets
+	/// 3. Print udp packets
+	#[arg(short, long, action = clap::ArgAction::Count)]
+	verbose: u8,
+}
+
+/// Holds one jitter buffer of interleaved stereo `f32` samples per inbound
+/// source and mixes them down into fixed-size 20 ms frames on demand.
+///
+/// Sources that underrun are padded with silence rather than stalling the
+/// whole mix, and each source's buffer is capped so a source that produces
+/// faster than the mixer drains can't build unbounded latency.
+#[derive(Default)]
+pub struct MixingBuffer {
+	sources: HashMap<SourceId, VecDeque<f32>>,
+}
+
+impl MixingBuffer {
+	pub fn new() -> Self { Self::default() }
+
+	/// Appends interleaved PCM samples for `source`, creating its buffer if
+	/// this is the first frame seen from it.
+	pub fn push(&mut self, source: SourceId, pcm: &[f32]) {
+		let buffer = self.sources.entry(source).or_default();
+		buffer.extend(pcm.iter().copied());
+		let overflow = buffer.len().saturating_sub(MAX_BUFFERED_SAMPLES);
+		if overflow > 0 {
+			buffer.drain(..overflow);
+		}
+	}
+
+	/// Removes a source's buffer, e.g. once a speaker has left the call.
+	pub fn remove(&mut self, source: SourceId) { self.sources.remove(&source); }
+
+	/// Pops one `FRAME_SAMPLES * CHANNELS` frame from every known source,
+	/// treating a short buffer's missing tail as silence, and sums them
+	/// sample-wise into a single mixed frame clamped to `[-1.0, 1.0]`.
+	pub fn mix_next_frame(&mut self) -> [f32; FRAME_SAMPLES * CHANNELS] {
+		let mut mixed = [0.0f32; FRAME_SAMPLES * CHANNELS];
+		for buffer in self.sources.values_mut() {
+			for slot in mixed.iter_mut() {
+				*slot += buffer.pop_front().unwrap_or(0.0);
+			}
+		}
+		for sample in mixed.iter_mut() {
+			*sample = sample.clamp(-1.0, 1.0);
+		}
+		mixed
+	}
+}
+
+#[tokio::main]
+async fn main() -> Result<()> { real_main().await }
+
+async fn real_main() -> Result<()> {
+	tracing_subscriber::fmt::init();
+	let args = Args::parse();
+
+	let con_config = Connection::build(args.address)
+		.log_commands(args.verbose >= 1)
+		.log_packets(args.verbose >= 2)
+		.log_udp_packets(args.verbose >= 3)
+		.password(args.password);
+
+	let id = Identity::new_from_str(
+		"MG0DAgeAAgEgAiAIXJBlj1hQbaH0Eq0DuLlCmH8bl+veTAO2+\
+		k9EQjEYSgIgNnImcmKo7ls5mExb6skfK2Tw+u54aeDr0OP1ITs\
+		C/50CIA8M5nmDBnmDM/gZ//4AAAAAAAAAAAAAAAAAAAAZRzOI",
+	)
+	.unwrap();
+	let con_config = con_config.identity(id);
+
+	let mut con = con_config.connect()?;
+	let r = con
+		.events()
+		.try_filter(|e| future::ready(matches!(e, StreamItem::BookEvents(_))))
+		.next()
+		.await;
+	if let Some(r) = r {
+		r?;
+	}
+
+	let (frame_tx, mut frame_rx) = mpsc::channel::<(SourceId, SourceEvent)>(256);
+	let listener =
+		TcpListener::bind(args.listen).await.with_context(|| format!("Failed to bind {}", args.listen))?;
+	tokio::spawn(accept_sources(listener, frame_tx));
+
+	let encoder = Encoder::new(
+		audiopus::SampleRate::Hz48000,
+		audiopus::Channels::Stereo,
+		audiopus::Application::Audio,
+	)
+	.context("Failed to create opus encoder")?;
+	let mut opus_out = [0u8; MAX_OPUS_FRAME_SIZE];
+
+	let mut mixer = MixingBuffer::new();
+	let mut ticker = time::interval(Duration::from_millis(20));
+	ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+	loop {
+		tokio::select! {
+			Some((source, event)) = frame_rx.recv() => {
+				match event {
+					SourceEvent::Frame(pcm) => mixer.push(source, &pcm),
+					SourceEvent::Closed => mixer.remove(source),
+				}
+			}
+			_ = ticker.tick() => {
+				let packet = encode_mixed_frame(&encoder, &mut opus_out, &mixer.mix_next_frame())?;
+				con.send_audio(packet)?;
+			}
+			_ = tokio::signal::ctrl_c() => {
+				break;
+			}
+		}
+	}
+
+	con.disconnect(DisconnectOptions::new())?;
+	con.events().for_each(|_| future::ready(())).await;
+	Ok(())
+}
+
+/// A message from a `read_source` task to the mixer loop: either a decoded
+/// PCM chunk, or notice that the source's connection has closed so its
+/// buffer can be dropped instead of lingering forever.
+enum SourceEvent {
+	Frame(Vec<f32>),
+	Closed,
+}
+
+/// Accepts one TCP connection per audio source and forwards decoded frames
+/// to the mixer task, tagging each connection with a sequential `SourceId`.
+async fn accept_sources(listener: TcpListener, frame_tx: mpsc::Sender<(SourceId, SourceEvent)>) {
+	let mut next_source_id: SourceId = 0;
+	loop {
+		let Ok((socket, _)) = listener.accept().await else { break };
+		let source = next_source_id;
+		next_source_id += 1;
+		let frame_tx = frame_tx.clone();
+		tokio::spawn(read_source(socket, source, frame_tx));
+	}
+}
+
+async fn read_source(mut socket: tokio::net::TcpStream, source: SourceId, frame_tx: mpsc::Sender<(SourceId, SourceEvent)>) {
+	let mut buf = vec![0u8; FRAME_SAMPLES * CHANNELS * 4];
+	// Bytes left over from the previous read that didn't complete a 4-byte
+	// f32 sample, carried at the front of `buf` for the next read to extend.
+	let mut pending = 0usize;
+	loop {
+		let n = match socket.read(&mut buf[pending..]).await {
+			Ok(0) | Err(_) => break,
+			Ok(n) => n,
+		};
+		let filled = pending + n;
+		let usable = filled - filled % 4;
+		let samples: Vec<f32> =
+			buf[..usable].chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+		if frame_tx.send((source, SourceEvent::Frame(samples))).await.is_err() {
+			break;
+		}
+		pending = filled - usable;
+		buf.copy_within(usable..filled, 0);
+	}
+	let _ = frame_tx.send((source, SourceEvent::Closed)).await;
+}
+
+fn encode_mixed_frame(
+	encoder: &Encoder,
+	opus_out: &mut [u8; MAX_OPUS_FRAME_SIZE],
+	mixed: &[f32; FRAME_SAMPLES * CHANNELS],
+) -> Result<OutPacket> {
+	let len = encoder.encode_float(mixed, opus_out).context("Failed to encode opus frame")?;
+	Ok(OutAudio::new(&AudioData::C2S { id: 0, codec: CodecType::OpusMusic, data: &opus_out[..len] }))
+}
@@ -0,0 +1,213 @@
+//! Pluggable PCM decoding shared by the audio examples.
+//!
+//! `decode_mp3_to_target_pcm` used to be hardwired to minimp3; this module
+//! generalizes that into an `AudioDecoder` trait with one implementation per
+//! container/codec, plus a loader that dispatches on the input file's
+//! extension so callers don't need to know the format up front.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use lewton::inside_ogg::OggStreamReader;
+use minimp3::{Decoder as Mp3RawDecoder, Error as Mp3Error};
+use ogg::reading::PacketReader;
+
+/// A source of PCM audio frames that can be decoded one frame at a time and,
+/// where the container supports it, seeked to an approximate position.
+pub trait AudioDecoder {
+	/// Returns the next decoded frame as interleaved or mono `f32` PCM (see
+	/// [`AudioDecoder::channels`]), or `None` once the stream is exhausted.
+	fn next_frame(&mut self) -> Result<Option<Vec<f32>>>;
+
+	/// The sample rate frames are decoded at.
+	fn sample_rate(&self) -> usize;
+
+	/// The channel count of the interleaved samples returned by `next_frame`.
+	fn channels(&self) -> usize;
+
+	/// Seeks to `ms` milliseconds from the start of the stream. Formats
+	/// without native seek support fall back to decoding and discarding
+	/// frames until the target position is reached.
+	fn seek(&mut self, ms: i64) -> Result<()>;
+}
+
+/// Opens `path` and returns a decoder for it, dispatching on file extension.
+pub fn open_decoder(path: &Path) -> Result<Box<dyn AudioDecoder>> {
+	match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()) {
+		Some(ext) if ext == "mp3" => Ok(Box::new(Mp3Decoder::open(path)?)),
+		Some(ext) if ext == "opus" => Ok(Box::new(OpusDecoder::open(path)?)),
+		Some(ext) if ext == "ogg" => open_ogg_decoder(path),
+		Some(ext) => bail!("Unsupported audio extension: {ext}"),
+		None => bail!("File {} has no extension to dispatch on", path.display()),
+	}
+}
+
+/// `.ogg` is ambiguous between Vorbis and Opus payloads, so peek at the first
+/// page's header packet to tell them apart.
+fn open_ogg_decoder(path: &Path) -> Result<Box<dyn AudioDecoder>> {
+	let mut probe = BufReader::new(File::open(path)?);
+	let mut reader = PacketReader::new(&mut probe);
+	let first_packet =
+		reader.read_packet().context("Failed to read first Ogg packet")?.ok_or_else(|| anyhow!("Empty Ogg file"))?;
+	if first_packet.data.starts_with(b"OpusHead") {
+		Ok(Box::new(OpusDecoder::open(path)?))
+	} else {
+		Ok(Box::new(VorbisDecoder::open(path)?))
+	}
+}
+
+/// Decodes MPEG-1/2 Layer III audio via `minimp3`. Has no native seek
+/// support, so seeking decodes and discards frames until the target.
+pub struct Mp3Decoder {
+	inner: Mp3RawDecoder<BufReader<File>>,
+	sample_rate: usize,
+	channels: usize,
+}
+
+impl Mp3Decoder {
+	pub fn open(path: &Path) -> Result<Self> {
+		let file = File::open(path)?;
+		let mut inner = Mp3RawDecoder::new(BufReader::new(file));
+		let frame = inner.next_frame().with_context(|| format!("Failed to probe {}", path.display()))?;
+		Ok(Self { inner, sample_rate: frame.sample_rate as usize, channels: frame.channels })
+	}
+}
+
+impl AudioDecoder for Mp3Decoder {
+	fn next_frame(&mut self) -> Result<Option<Vec<f32>>> {
+		match self.inner.next_frame() {
+			Ok(frame) => {
+				self.sample_rate = frame.sample_rate as usize;
+				self.channels = frame.channels;
+				Ok(Some(frame.data.iter().map(|s| *s as f32 / 32768.0).collect()))
+			}
+			Err(Mp3Error::Eof) => Ok(None),
+			Err(error) => Err(anyhow!("mp3 decode error: {error}")),
+		}
+	}
+
+	fn sample_rate(&self) -> usize { self.sample_rate }
+
+	fn channels(&self) -> usize { self.channels }
+
+	fn seek(&mut self, ms: i64) -> Result<()> { seek_by_discarding(self, ms) }
+}
+
+/// Decodes Ogg Vorbis audio via `lewton`. Supports native seeking by
+/// translating a millisecond offset into an absolute granule position.
+pub struct VorbisDecoder {
+	inner: OggStreamReader<BufReader<File>>,
+}
+
+impl VorbisDecoder {
+	pub fn open(path: &Path) -> Result<Self> {
+		let file = File::open(path)?;
+		let inner = OggStreamReader::new(BufReader::new(file))
+			.with_context(|| format!("Failed to open Vorbis stream {}", path.display()))?;
+		Ok(Self { inner })
+	}
+}
+
+impl AudioDecoder for VorbisDecoder {
+	fn next_frame(&mut self) -> Result<Option<Vec<f32>>> {
+		let Some(packet) = self.inner.read_dec_packet_itl().context("Vorbis decode error")? else {
+			return Ok(None);
+		};
+		Ok(Some(packet.into_iter().map(|s| s as f32 / 32768.0).collect()))
+	}
+
+	fn sample_rate(&self) -> usize { self.inner.ident_hdr.audio_sample_rate as usize }
+
+	fn channels(&self) -> usize { self.inner.ident_hdr.audio_channels as usize }
+
+	fn seek(&mut self, ms: i64) -> Result<()> {
+		let granule = (ms as i64 * self.sample_rate() as i64 / 1000).max(0) as u64;
+		self.inner.seek_absgp_pg(granule).context("Failed to seek Vorbis stream")
+	}
+}
+
+/// Decodes Ogg Opus audio by unwrapping Ogg pages and running the raw Opus
+/// packets through an `audiopus` decoder. Has no cheap native seek (the
+/// granule position in the header is enough to locate a page, but lewton's
+/// crate does the heavy lifting for Vorbis only), so seeking falls back to
+/// decode-and-discard.
+pub struct OpusDecoder {
+	reader: PacketReader<BufReader<File>>,
+	decoder: audiopus::coder::Decoder,
+	channels: usize,
+	/// Remaining pre-skip samples still to discard, in interleaved-float
+	/// units (i.e. already multiplied by `channels`), so draining never has
+	/// to divide back down into per-channel-frame units mid-stream.
+	pre_skip_floats: usize,
+}
+
+const OPUS_SAMPLE_RATE: usize = 48_000;
+
+impl OpusDecoder {
+	pub fn open(path: &Path) -> Result<Self> {
+		let file = File::open(path)?;
+		let mut reader = PacketReader::new(BufReader::new(file));
+		let header = reader
+			.read_packet()
+			.context("Failed to read OpusHead packet")?
+			.ok_or_else(|| anyhow!("Empty Ogg Opus file"))?;
+		if !header.data.starts_with(b"OpusHead") {
+			bail!("{} is not an Ogg Opus stream", path.display());
+		}
+		let channels = header.data[9] as usize;
+		let pre_skip = u16::from_le_bytes([header.data[10], header.data[11]]) as usize;
+		let pre_skip_floats = pre_skip * channels;
+
+		// Skip the OpusTags comment header packet.
+		reader.read_packet().context("Failed to read OpusTags packet")?;
+
+		let opus_channels =
+			if channels == 1 { audiopus::Channels::Mono } else { audiopus::Channels::Stereo };
+		let decoder = audiopus::coder::Decoder::new(audiopus::SampleRate::Hz48000, opus_channels)
+			.context("Failed to create Opus decoder")?;
+		Ok(Self { reader, decoder, channels, pre_skip_floats })
+	}
+}
+
+impl AudioDecoder for OpusDecoder {
+	fn next_frame(&mut self) -> Result<Option<Vec<f32>>> {
+		let Some(packet) = self.reader.read_packet().context("Failed to read Ogg Opus packet")? else {
+			return Ok(None);
+		};
+		// One packet is at most one 120ms frame at 48kHz.
+		let mut out = vec![0.0f32; OPUS_SAMPLE_RATE / 1000 * 120 * self.channels];
+		let len = self
+			.decoder
+			.decode_float(Some(&packet.data), &mut out, false)
+			.context("Opus decode error")?;
+		out.truncate(len * self.channels);
+		if self.pre_skip_floats > 0 {
+			let skip = self.pre_skip_floats.min(out.len());
+			out.drain(..skip);
+			self.pre_skip_floats -= skip;
+		}
+		Ok(Some(out))
+	}
+
+	fn sample_rate(&self) -> usize { OPUS_SAMPLE_RATE }
+
+	fn channels(&self) -> usize { self.channels }
+
+	fn seek(&mut self, ms: i64) -> Result<()> { seek_by_discarding(self, ms) }
+}
+
+/// Shared fallback for decoders without native seek: decode and discard
+/// frames until the target position (in output samples) is reached.
+fn seek_by_discarding(decoder: &mut (impl AudioDecoder + ?Sized), ms: i64) -> Result<()> {
+	let target_samples = (ms.max(0) as u64 * decoder.sample_rate() as u64 / 1000) as usize;
+	let mut consumed = 0usize;
+	while consumed < target_samples {
+		match decoder.next_frame()? {
+			Some(frame) => consumed += frame.len() / decoder.channels().max(1),
+			None => break,
+		}
+	}
+	Ok(())
+}
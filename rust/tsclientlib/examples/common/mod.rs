@@ -0,0 +1,7 @@
+//! Shared helper code for the audio examples, kept out of `examples/`'s top
+//! level so Cargo's example auto-discovery doesn't try to build each of
+//! these as its own example binary (it only scans top-level `examples/*.rs`
+//! files and `examples/*/main.rs`, never other files in a subdirectory).
+
+pub mod audio_decode;
+pub mod sample_source;